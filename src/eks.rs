@@ -0,0 +1,127 @@
+//! In-process EKS IAM authentication token generation, mirroring what `aws eks get-token`
+//! (and the `aws-iam-authenticator` binary) do, so the kube-rs client can authenticate to
+//! EKS clusters without shelling out to the `aws` CLI.
+//!
+//! The token is a presigned STS `GetCallerIdentity` URL, signed with SigV4 using the
+//! standard AWS credential provider chain, carrying the `x-k8s-aws-id` header the EKS
+//! control plane checks against the cluster name.
+
+use anyhow::{Context, Result};
+use aws_config::BehaviorVersion;
+use aws_sigv4::http_request::{SignableBody, SignableRequest, SignatureLocation, SigningSettings, sign};
+use aws_sigv4::sign::v4;
+use base64::Engine as _;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use std::time::{Duration, SystemTime};
+
+const TOKEN_PREFIX: &str = "k8s-aws-v1.";
+const TOKEN_EXPIRY: Duration = Duration::from_secs(60);
+
+/// Resolves AWS credentials from the standard provider chain (env vars, profile,
+/// IRSA/web-identity), optionally pinned to a specific profile/region, and returns them as
+/// `(name, value)` environment variable pairs. Used to inject credentials into the
+/// environment of a spawned `mirrord exec` process and into the kube client's own
+/// environment for kubeconfigs that rely on exec-based auth (e.g. `aws eks get-token`).
+/// Fails fast with a clear error if resolution doesn't complete within `resolve_timeout`.
+pub async fn resolve_credential_env(
+    profile: Option<&str>,
+    region: Option<&str>,
+    resolve_timeout: Duration,
+) -> Result<Vec<(String, String)>> {
+    let mut loader = aws_config::defaults(BehaviorVersion::latest());
+    if let Some(profile) = profile {
+        loader = loader.profile_name(profile);
+    }
+    if let Some(region) = region {
+        loader = loader.region(aws_config::Region::new(region.to_string()));
+    }
+    let sdk_config = tokio::time::timeout(resolve_timeout, loader.load())
+        .await
+        .context("timed out resolving AWS credentials")?;
+
+    let provider = sdk_config
+        .credentials_provider()
+        .context("no AWS credentials provider configured")?;
+    let credentials = tokio::time::timeout(resolve_timeout, provider.provide_credentials())
+        .await
+        .context("timed out resolving AWS credentials")?
+        .context("failed to resolve AWS credentials from the provider chain")?;
+
+    let mut env = vec![
+        (
+            "AWS_ACCESS_KEY_ID".to_string(),
+            credentials.access_key_id().to_string(),
+        ),
+        (
+            "AWS_SECRET_ACCESS_KEY".to_string(),
+            credentials.secret_access_key().to_string(),
+        ),
+    ];
+    if let Some(session_token) = credentials.session_token() {
+        env.push(("AWS_SESSION_TOKEN".to_string(), session_token.to_string()));
+    }
+    if let Some(profile) = profile {
+        env.push(("AWS_PROFILE".to_string(), profile.to_string()));
+    }
+    if let Some(region) = region {
+        env.push(("AWS_REGION".to_string(), region.to_string()));
+    }
+    Ok(env)
+}
+
+/// Mints a bearer token the EKS control plane accepts, for the given cluster in the given
+/// AWS region, using credentials resolved from the default `aws-config` provider chain
+/// (env vars, profile, IRSA/web-identity, etc).
+pub async fn generate_token(cluster_name: &str, region: &str) -> Result<String> {
+    let sdk_config = aws_config::defaults(BehaviorVersion::latest())
+        .region(aws_config::Region::new(region.to_string()))
+        .load()
+        .await;
+
+    let identity = sdk_config
+        .credentials_provider()
+        .context("no AWS credentials provider configured")?
+        .provide_credentials()
+        .await
+        .context("failed to resolve AWS credentials from the default provider chain")?
+        .into();
+
+    let mut signing_settings = SigningSettings::default();
+    signing_settings.expires_in = Some(TOKEN_EXPIRY);
+    signing_settings.signature_location = SignatureLocation::QueryParams;
+
+    let signing_params: aws_sigv4::sign::v4::signing_params::SigningParams<'_> = v4::SigningParams::builder()
+        .identity(&identity)
+        .region(region)
+        .name("sts")
+        .time(SystemTime::now())
+        .settings(signing_settings)
+        .build()
+        .context("failed to build SigV4 signing params")?
+        .into();
+
+    let url = format!("https://sts.{region}.amazonaws.com/?Action=GetCallerIdentity&Version=2011-06-15");
+    let signable_request = SignableRequest::new(
+        "GET",
+        &url,
+        std::iter::once(("x-k8s-aws-id", cluster_name)),
+        SignableBody::Bytes(&[]),
+    )
+    .context("failed to build signable STS request")?;
+
+    let (signing_instructions, _signature) = sign(signable_request, &signing_params)
+        .context("failed to presign STS GetCallerIdentity request")?
+        .into_parts();
+
+    let mut request = http::Request::builder()
+        .uri(&url)
+        .body(())
+        .context("failed to build STS request")?;
+    signing_instructions.apply_to_request_http1x(&mut request);
+
+    let presigned_url = request.uri().to_string();
+    Ok(format!(
+        "{TOKEN_PREFIX}{}",
+        URL_SAFE_NO_PAD.encode(presigned_url)
+    ))
+}
@@ -0,0 +1,212 @@
+//! Kubernetes API access used to resolve a deployment down to a running pod.
+//!
+//! This replaces shelling out to the `kubectl` binary with a native client built on
+//! `kube` + `k8s-openapi`, so the server works even when `kubectl` isn't on `PATH` and so
+//! label selection follows the deployment's actual `spec.selector.matchLabels` instead of
+//! assuming `app=<name>`.
+
+use anyhow::{Context, Result, anyhow};
+use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::core::v1::Pod;
+use kube::{
+    Api, Client,
+    api::ListParams,
+    config::{KubeConfigOptions, Kubeconfig},
+};
+use std::time::Duration;
+
+/// Timeout for resolving AWS credentials to inject into the kube client's exec-auth flow.
+/// Kept short since this just primes the environment before kubeconfig/exec-plugin setup,
+/// not the pod-resolution call itself.
+const CREDENTIAL_RESOLVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Env vars that, when all three are set, select the native EKS IAM auth path over kubeconfig /
+/// in-cluster discovery. See [`crate::eks`] for how the bearer token itself is minted.
+const EKS_CLUSTER_NAME_VAR: &str = "MIRRORD_MCP_EKS_CLUSTER_NAME";
+const EKS_REGION_VAR: &str = "MIRRORD_MCP_EKS_REGION";
+const EKS_API_SERVER_VAR: &str = "MIRRORD_MCP_EKS_API_SERVER";
+
+/// Resolve a deployment's real `matchLabels` selector, list matching pods, and return the
+/// name of one. Falls back to shelling out to `kubectl` if resolution through the
+/// Kubernetes API fails for any reason (e.g. no kubeconfig / not running in-cluster, or no
+/// ready pod found), unless `MIRRORD_MCP_DISABLE_KUBECTL_FALLBACK` is set, which keeps this
+/// a drop-in replacement for the previous behavior. Shared by every entry point that needs to turn a
+/// deployment name into a concrete pod (the tool executor, the axum `run_service` handler).
+///
+/// `aws_profile`/`aws_region` are forwarded to [`build_client`] so an explicit override
+/// reaches the kube client's own exec-auth flow, not just the spawned `mirrord exec`
+/// process.
+pub async fn resolve_target(
+    deployment: &str,
+    namespace: &str,
+    aws_profile: Option<&str>,
+    aws_region: Option<&str>,
+) -> Result<String> {
+    match get_pod_name_via_api(deployment, namespace, aws_profile, aws_region).await {
+        Ok(pod_name) => Ok(pod_name),
+        Err(e) if kubectl_fallback_enabled() => {
+            tracing::warn!(error = %e, "kube API resolution failed, falling back to kubectl");
+            get_pod_name_via_kubectl(deployment, namespace).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn get_pod_name_via_api(
+    deployment: &str,
+    namespace: &str,
+    aws_profile: Option<&str>,
+    aws_region: Option<&str>,
+) -> Result<String> {
+    let client = build_client(aws_profile, aws_region).await?;
+
+    let deployments: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+    let deployment_obj = deployments
+        .get(deployment)
+        .await
+        .with_context(|| format!("failed to fetch deployment {deployment}/{namespace}"))?;
+
+    let match_labels = deployment_obj
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.selector.match_labels.as_ref())
+        .ok_or_else(|| anyhow!("deployment {deployment} has no spec.selector.matchLabels"))?;
+
+    let selector = match_labels
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let pods: Api<Pod> = Api::namespaced(client, namespace);
+    let lp = ListParams::default().labels(&selector);
+    let pod_list = pods
+        .list(&lp)
+        .await
+        .with_context(|| format!("failed to list pods matching '{selector}' in {namespace}"))?;
+
+    pod_list
+        .items
+        .into_iter()
+        .find(is_running_and_ready)
+        .and_then(|pod| pod.metadata.name)
+        .ok_or_else(|| {
+            anyhow!("no running, ready pod found for deployment {deployment} (selector '{selector}')")
+        })
+}
+
+fn is_running_and_ready(pod: &Pod) -> bool {
+    let Some(status) = &pod.status else {
+        return false;
+    };
+    if status.phase.as_deref() != Some("Running") {
+        return false;
+    }
+    status
+        .container_statuses
+        .as_ref()
+        .is_some_and(|statuses| statuses.iter().all(|c| c.ready))
+}
+
+/// Builds the kube client used to resolve targets. When the native EKS IAM path isn't
+/// configured, injects AWS credentials resolved from `aws_profile`/`aws_region` (or the
+/// default provider chain) into the process environment first, so a kubeconfig whose user
+/// relies on exec-based auth (e.g. `aws eks get-token`) sees them too - mirroring the
+/// credentials already injected into the spawned `mirrord exec` process. Best-effort: a
+/// cluster with no AWS credentials at all (the common non-EKS case) just proceeds with
+/// whatever ambient auth the kubeconfig/exec plugin already has.
+async fn build_client(aws_profile: Option<&str>, aws_region: Option<&str>) -> Result<Client> {
+    if let Some(client) = build_eks_client().await? {
+        return Ok(client);
+    }
+
+    match crate::eks::resolve_credential_env(aws_profile, aws_region, CREDENTIAL_RESOLVE_TIMEOUT)
+        .await
+    {
+        Ok(credential_env) => {
+            for (key, value) in credential_env {
+                // SAFETY: single-threaded initialization race aside, mirrord-mcp doesn't
+                // otherwise read/write this env var concurrently from other threads.
+                unsafe { std::env::set_var(key, value) };
+            }
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to resolve AWS credentials for kube client, continuing without them");
+        }
+    }
+
+    match Kubeconfig::read() {
+        Ok(kubeconfig) => {
+            let options = KubeConfigOptions::default();
+            let config =
+                kube::Config::from_custom_kubeconfig(kubeconfig, &options)
+                    .await
+                    .context("failed to build kube Config from kubeconfig")?;
+            Client::try_from(config).context("failed to construct kube Client")
+        }
+        Err(_) => Client::try_default()
+            .await
+            .context("failed to construct in-cluster kube Client"),
+    }
+}
+
+/// When `MIRRORD_MCP_EKS_CLUSTER_NAME`, `MIRRORD_MCP_EKS_REGION`, and
+/// `MIRRORD_MCP_EKS_API_SERVER` are all set, authenticates directly against the named EKS
+/// cluster using an in-process, SigV4-presigned IAM token instead of reading a kubeconfig.
+/// Returns `Ok(None)` when the env vars aren't configured, so callers fall back to the
+/// usual kubeconfig/in-cluster discovery.
+async fn build_eks_client() -> Result<Option<Client>> {
+    let (cluster_name, region, api_server) = match (
+        std::env::var(EKS_CLUSTER_NAME_VAR),
+        std::env::var(EKS_REGION_VAR),
+        std::env::var(EKS_API_SERVER_VAR),
+    ) {
+        (Ok(cluster_name), Ok(region), Ok(api_server)) => (cluster_name, region, api_server),
+        _ => return Ok(None),
+    };
+
+    let token = crate::eks::generate_token(&cluster_name, &region)
+        .await
+        .with_context(|| format!("failed to generate EKS IAM token for cluster {cluster_name}"))?;
+
+    let mut config = kube::Config::new(
+        api_server
+            .parse()
+            .with_context(|| format!("invalid EKS API server URL: {api_server}"))?,
+    );
+    config.auth_info.token = Some(token.into());
+
+    Client::try_from(config)
+        .map(Some)
+        .context("failed to construct kube Client from EKS auth")
+}
+
+fn kubectl_fallback_enabled() -> bool {
+    std::env::var("MIRRORD_MCP_DISABLE_KUBECTL_FALLBACK").is_err()
+}
+
+async fn get_pod_name_via_kubectl(deployment: &str, namespace: &str) -> Result<String> {
+    let output = tokio::process::Command::new("kubectl")
+        .arg("get")
+        .arg("pods")
+        .arg("-n")
+        .arg(namespace)
+        .arg("-l")
+        .arg(format!("app={deployment}"))
+        .arg("-o")
+        .arg("jsonpath={.items[0].metadata.name}")
+        .output()
+        .await
+        .context("failed to execute kubectl")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("kubectl failed: {stderr}"));
+    }
+
+    let pod_name = String::from_utf8(output.stdout).context("invalid pod name from kubectl")?;
+    if pod_name.is_empty() {
+        return Err(anyhow!("no pod found for deployment: {deployment}"));
+    }
+    Ok(pod_name)
+}
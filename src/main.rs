@@ -12,11 +12,126 @@ use tracing::{debug, error, info, warn};
 use tracing_subscriber;
 use uuid::Uuid;
 
+/// The kind of workload `deployment` names. Mirrord resolves deployment/statefulset/
+/// rollout/service targets to a pod itself, so only the `Pod` kind needs us to do that
+/// resolution ourselves.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TargetKind {
+    #[default]
+    Pod,
+    Deployment,
+    StatefulSet,
+    Rollout,
+    Service,
+}
+
+impl TargetKind {
+    fn as_mirrord_str(&self) -> &'static str {
+        match self {
+            TargetKind::Pod => "pod",
+            TargetKind::Deployment => "deployment",
+            TargetKind::StatefulSet => "statefulset",
+            TargetKind::Rollout => "rollout",
+            TargetKind::Service => "service",
+        }
+    }
+}
+
+fn default_namespace() -> String {
+    "default".to_string()
+}
+
 #[derive(Serialize, Deserialize)]
 struct RunServiceRequest {
     code: String,
-    deployment: String,
+    /// When omitted, mirrord runs targetless: an independent agent with cluster
+    /// network/DNS access but no mirrored pod.
+    #[serde(default)]
+    deployment: Option<String>,
     mirrord_config: String,
+    #[serde(default = "default_namespace")]
+    namespace: String,
+    #[serde(default)]
+    target_kind: TargetKind,
+    /// When true, compile the project and return structured compiler diagnostics without
+    /// invoking `mirrord exec`.
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+struct DiagnosticSpan {
+    file_name: String,
+    line_start: i32,
+    column_start: i32,
+}
+
+#[derive(Serialize)]
+struct Diagnostic {
+    level: String,
+    code: Option<String>,
+    rendered: Option<String>,
+    spans: Vec<DiagnosticSpan>,
+}
+
+#[derive(Serialize)]
+struct BuildPlan {
+    dry_run: bool,
+    success: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Runs `cargo build --release --message-format=json` in `project_dir` and parses the
+/// emitted `compiler-message` lines into a structured diagnostics report, without ever
+/// invoking `mirrord exec`. Uses `--release` so the dry-run diagnostics come from the same
+/// build profile as the real compile below, instead of wastefully compiling debug first.
+fn build_plan(project_dir: &str) -> Result<BuildPlan, StatusCode> {
+    let output = Command::new("cargo")
+        .current_dir(project_dir)
+        .args(["build", "--release", "--message-format=json"])
+        .output()
+        .map_err(|e| {
+            error!("Failed to execute cargo build: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut diagnostics = Vec::new();
+    for line in stdout.lines() {
+        let Ok(msg) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if msg["reason"] != "compiler-message" {
+            continue;
+        }
+        let message = &msg["message"];
+        let spans = message["spans"]
+            .as_array()
+            .map(|spans| {
+                spans
+                    .iter()
+                    .map(|span| DiagnosticSpan {
+                        file_name: span["file_name"].as_str().unwrap_or_default().to_string(),
+                        line_start: span["line_start"].as_i64().unwrap_or_default() as i32,
+                        column_start: span["column_start"].as_i64().unwrap_or_default() as i32,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        diagnostics.push(Diagnostic {
+            level: message["level"].as_str().unwrap_or_default().to_string(),
+            code: message["code"]["code"].as_str().map(str::to_string),
+            rendered: message["rendered"].as_str().map(str::to_string),
+            spans,
+        });
+    }
+
+    Ok(BuildPlan {
+        dry_run: true,
+        success: output.status.success(),
+        diagnostics,
+    })
 }
 
 #[derive(Serialize, Deserialize)]
@@ -53,64 +168,53 @@ async fn tools() -> Result<Json<Vec<ToolDefinition>>, StatusCode> {
     Ok(Json(tools))
 }
 
-fn get_pod_name(deployment: &str, namespace: &str) -> Result<String, StatusCode> {
-    let output = Command::new("kubectl")
-        .arg("get")
-        .arg("pods")
-        .arg("-n")
-        .arg(namespace)
-        .arg("-l")
-        .arg(format!("app={}", deployment))
-        .arg("-o")
-        .arg("jsonpath={.items[0].metadata.name}")
-        .output()
+async fn get_pod_name(deployment: &str, namespace: &str) -> Result<String, StatusCode> {
+    crate::kubectl::resolve_target(deployment, namespace, None, None)
+        .await
         .map_err(|e| {
-            error!("Failed to run kubectl: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-    if output.status.success() {
-        let pod_name = String::from_utf8(output.stdout).map_err(|e| {
-            error!("Invalid pod name: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-        if pod_name.is_empty() {
-            error!("No pod found for deployment: {}", deployment);
-            Err(StatusCode::NOT_FOUND)
-        } else {
-            info!("Found pod: {}", pod_name);
-            Ok(pod_name)
-        }
-    } else {
-        let stderr = String::from_utf8(output.stderr).map_err(|e| {
-            error!("Invalid kubectl error: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-        error!("kubectl failed: {}", stderr);
-        Err(StatusCode::INTERNAL_SERVER_ERROR)
-    }
+            error!(error = %e, "Failed to resolve pod for deployment");
+            StatusCode::NOT_FOUND
+        })
 }
 
 async fn run_service(Json(req): Json<RunServiceRequest>) -> Result<String, StatusCode> {
-    // Fetch the pod name for the deployment
-    let pod_name = get_pod_name(&req.deployment, "default").map_err(|e| {
-        error!("Failed to get pod name: {}", e);
-        StatusCode::NOT_FOUND
-    })?;
-
     // Update mirrord config with the pod name
     let config: serde_json::Value = serde_json::from_str(&req.mirrord_config).map_err(|e| {
         error!("Failed to parse mirrord config: {}", e);
         StatusCode::BAD_REQUEST
     })?;
 
-    let updated_config = serde_json::json!({
-        "target": {
-            "namespace": "default",
-            "path": format!("pod/{}", pod_name)
-        },
-        "feature": config["feature"]
-    });
+    let updated_config = match req.deployment.as_deref().filter(|d| !d.is_empty()) {
+        Some(deployment) => {
+            let path = match req.target_kind {
+                TargetKind::Pod => {
+                    // Fetch the pod name for the deployment
+                    let pod_name = get_pod_name(deployment, &req.namespace).await?;
+                    format!("pod/{}", pod_name)
+                }
+                // mirrord resolves these kinds to a pod itself; skip the round-trip.
+                TargetKind::Deployment
+                | TargetKind::StatefulSet
+                | TargetKind::Rollout
+                | TargetKind::Service => {
+                    format!("{}/{}", req.target_kind.as_mirrord_str(), deployment)
+                }
+            };
+            serde_json::json!({
+                "target": {
+                    "namespace": &req.namespace,
+                    "path": path
+                },
+                "feature": config["feature"]
+            })
+        }
+        None => {
+            info!("No deployment specified, running targetless");
+            serde_json::json!({
+                "feature": config["feature"]
+            })
+        }
+    };
     let config_str = serde_json::to_string(&updated_config).map_err(|e| {
         error!("Failed to serialize mirrord config: {}", e);
         StatusCode::BAD_REQUEST
@@ -150,6 +254,17 @@ anyhow = "1.0"
     })?;
     debug!("Wrote main.rs with code length: {} bytes", req.code.len());
 
+    if req.dry_run {
+        info!("Dry run: compiling {} without invoking mirrord", project_dir);
+        let plan = build_plan(&project_dir);
+        let _ = std::fs::remove_dir_all(&project_dir);
+        let plan = plan?;
+        return serde_json::to_string_pretty(&plan).map_err(|e| {
+            error!("Failed to serialize build plan: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        });
+    }
+
     // Compile
     info!("Compiling Rust code in {}", project_dir);
     let compile_output = Command::new("cargo")
@@ -195,7 +310,7 @@ anyhow = "1.0"
     debug!("Wrote mirrord config to {}", config_path);
 
     // Run mirrord
-    info!("Executing mirrord for pod: {}", pod_name);
+    info!("Executing mirrord with config: {}", config_str);
     let output = Command::new("mirrord")
         .arg("exec")
         .arg("--config-file")
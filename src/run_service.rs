@@ -7,24 +7,75 @@ use rmcp::{
     schemars, tool,
 };
 use serde::{Deserialize, Serialize};
-use std::{io::Write, path::Path, process::Command};
+use sha2::{Digest, Sha256};
+use std::sync::LazyLock;
+use std::{io::Write, path::Path};
 use tempfile::NamedTempFile;
-use uuid::Uuid;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+use crate::tools::executor::stream_child_output;
 
 #[derive(Serialize, Deserialize)]
 struct RunServiceRequest {}
+
+/// Serializes access to the shared build workspace (`project_dir` below). Without this,
+/// two overlapping calls with different `code` race on the same `src/main.rs`/`target/`:
+/// one can overwrite the other's source mid-compile, and the binary that gets cached under
+/// a given `code_hash` may actually have been compiled from a different caller's code.
+static BUILD_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+/// The kind of workload `deployment` names. Mirrord resolves deployment/rollout/job/
+/// statefulset targets to a pod itself, so only `Pod` needs us to do that resolution
+/// ourselves via the Kubernetes API (with kubectl as a fallback).
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetType {
+    #[default]
+    Pod,
+    Deployment,
+    Rollout,
+    Job,
+    StatefulSet,
+}
+
+impl TargetType {
+    fn as_mirrord_str(&self) -> &'static str {
+        match self {
+            TargetType::Pod => "pod",
+            TargetType::Deployment => "deployment",
+            TargetType::Rollout => "rollout",
+            TargetType::Job => "job",
+            TargetType::StatefulSet => "statefulset",
+        }
+    }
+}
+
+fn default_namespace() -> String {
+    "default".to_string()
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct MirrordRequest {
     #[schemars(
         description = "Complete rust code using only reqwest::blocking::get, serde::Deserialize, serde_json, and anyhow::Result. The resulting binary is run against the cluster."
     )]
     code: String,
-    #[schemars(description = "Kubernetes deployment name.")]
-    deployment: String,
+    #[schemars(
+        description = "Kubernetes deployment name. When omitted, mirrord runs targetless: an independent agent with cluster network/DNS access but no mirrored pod."
+    )]
+    deployment: Option<String>,
     #[schemars(
         description = "Mirrord config in JSON format.e.g., '{\"feature\": {\"network\": {\"incoming\": {\"mode\": \"mirror\", \"ports\": [ 8888 ] } } }'."
     )]
     mirrord_config: String,
+    #[schemars(description = "Kubernetes namespace the target lives in. Defaults to \"default\".")]
+    #[serde(default = "default_namespace")]
+    namespace: String,
+    #[schemars(
+        description = "The kind of workload `deployment` names: pod, deployment, rollout, job, or statefulset. Defaults to pod, which is resolved to a running pod via the Kubernetes API; the others are passed straight through as mirrord target paths."
+    )]
+    #[serde(default)]
+    target_type: TargetType,
 }
 
 #[derive(Debug, Clone)]
@@ -41,11 +92,11 @@ impl MirrordService {
     #[tool(
         description = "Run a rust binary against a Kubernetes service using mirrord to mirror traffic"
     )]
-    fn run_service(
+    async fn run_service(
         &self,
         #[tool(aggr)] request: MirrordRequest,
     ) -> Result<CallToolResult, McpError> {
-        let result = run_service(request)?;
+        let result = run_service(request).await?;
         Ok(CallToolResult::success(vec![Content::text(result)]))
     }
 }
@@ -61,91 +112,175 @@ impl ServerHandler for MirrordService {
     }
 }
 
-fn get_pod_name(deployment: &str, namespace: &str) -> Result<String, McpError> {
-    let output = Command::new("kubectl")
-        .arg("get")
-        .arg("pods")
-        .arg("-n")
-        .arg(namespace)
-        .arg("-l")
-        .arg(format!("app={}", deployment))
-        .arg("-o")
-        .arg("jsonpath={.items[0].metadata.name}")
-        .output()
-        .map_err(|e| {
-            tracing::error!(error = %e, "Failed to run kubectl");
-            McpError::internal_error("Failed to execute kubectl command".to_string(), None)
-        })?;
+#[derive(Debug, Serialize)]
+struct DiagnosticSpan {
+    file_name: String,
+    line_start: i64,
+    column_start: i64,
+}
 
-    if output.status.success() {
-        let pod_name = String::from_utf8(output.stdout).map_err(|e| {
-            tracing::error!(error = %e, "Invalid pod name");
+#[derive(Debug, Serialize)]
+struct Diagnostic {
+    level: String,
+    code: Option<String>,
+    rendered: Option<String>,
+    spans: Vec<DiagnosticSpan>,
+}
+
+/// Parses cargo's `--message-format=json` stdout, keeping only `compiler-message` lines
+/// and extracting the fields an MCP client needs to act on a failure: which level, which
+/// file/line, and the rendered message, rather than a single opaque stderr blob.
+fn parse_compiler_diagnostics(stdout: &[u8]) -> Vec<Diagnostic> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|msg| msg["reason"] == "compiler-message")
+        .map(|msg| {
+            let message = &msg["message"];
+            let spans = message["spans"]
+                .as_array()
+                .map(|spans| {
+                    spans
+                        .iter()
+                        .map(|span| DiagnosticSpan {
+                            file_name: span["file_name"].as_str().unwrap_or_default().to_string(),
+                            line_start: span["line_start"].as_i64().unwrap_or_default(),
+                            column_start: span["column_start"].as_i64().unwrap_or_default(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            Diagnostic {
+                level: message["level"].as_str().unwrap_or_default().to_string(),
+                code: message["code"]["code"].as_str().map(str::to_string),
+                rendered: message["rendered"].as_str().map(str::to_string),
+                spans,
+            }
+        })
+        .collect()
+}
+
+async fn get_pod_name(deployment: &str, namespace: &str) -> Result<String, McpError> {
+    crate::kubectl::resolve_target(deployment, namespace, None, None)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to resolve pod for deployment");
             McpError::internal_error(
-                "Failed to parse pod name from kubectl output".to_string(),
+                format!("Failed to resolve pod for deployment {}: {}", deployment, e),
                 None,
             )
-        })?;
-        if pod_name.is_empty() {
-            tracing::error!("No pod found for deployment");
-            Err(McpError::internal_error(
-                format!("No pod found for deployment: {}", deployment),
-                None,
-            ))
-        } else {
-            tracing::info!("Found pod: {}", pod_name);
-            Ok(pod_name)
-        }
-    } else {
-        let stderr = String::from_utf8(output.stderr).map_err(|e| {
-            tracing::error!(error = %e, "Failed to parse kubectl error");
-            McpError::internal_error("Failed to parse kubectl error output".to_string(), None)
-        })?;
-        tracing::error!(error = "kubectl failed", stderr);
-        Err(McpError::internal_error(
-            format!("kubectl failed {}", stderr),
-            None,
-        ))
-    }
+        })
 }
 
-fn run_service(request: MirrordRequest) -> Result<String, McpError> {
-    // Fetch the pod name for the deployment
-    let pod_name = get_pod_name(&request.deployment, "default").map_err(|e| {
-        tracing::error!(error = %e, "Failed to get pod name");
-        e
-    })?;
-
+async fn run_service(request: MirrordRequest) -> Result<String, McpError> {
     // Update mirrord config with the pod name
     let config: serde_json::Value = serde_json::from_str(&request.mirrord_config).map_err(|e| {
         tracing::error!(error = %e, "Failed to parse mirrord config");
         McpError::internal_error("Failed to parse mirrord config".to_string(), None)
     })?;
 
-    let updated_config = serde_json::json!({
-        "target": {
-            "namespace": "default",
-            "path": format!("pod/{}", pod_name)
-        },
-        "feature": config["feature"]
-    });
+    let updated_config = match request.deployment.as_deref().filter(|d| !d.is_empty()) {
+        Some(deployment) => {
+            // Only the Pod kind needs us to resolve a concrete, running pod ourselves;
+            // mirrord resolves deployment/rollout/job/statefulset targets itself, so we
+            // can build the target path directly and skip the kubectl round-trip (and its
+            // fragile `app=` label assumption and single-replica limitation).
+            let path = match request.target_type {
+                TargetType::Pod => {
+                    let pod_name =
+                        get_pod_name(deployment, &request.namespace)
+                            .await
+                            .map_err(|e| {
+                                tracing::error!(error = %e, "Failed to get pod name");
+                                e
+                            })?;
+                    format!("pod/{}", pod_name)
+                }
+                other => format!("{}/{}", other.as_mirrord_str(), deployment),
+            };
+
+            // Stealing from the live pod would black-hole traffic for every other user of
+            // the deployment. When steal mode is requested, target a copy of the pod
+            // instead (mirrord's `copy_target`), so concurrent sessions can each steal
+            // their own slice of traffic (typically scoped further by an http_filter)
+            // without affecting one another.
+            let incoming_mode = config["feature"]["network"]["incoming"]["mode"].as_str();
+            let is_steal = incoming_mode == Some("steal");
+            if is_steal {
+                tracing::info!("Steal mode requested, targeting a copy of the pod");
+            }
+
+            serde_json::json!({
+                "target": {
+                    "namespace": &request.namespace,
+                    "path": path,
+                    "copy_target": is_steal
+                },
+                "feature": config["feature"]
+            })
+        }
+        None => {
+            tracing::info!("No deployment specified, running targetless");
+            serde_json::json!({
+                "feature": config["feature"]
+            })
+        }
+    };
     let config_str = serde_json::to_string(&updated_config).map_err(|e| {
         tracing::error!(error = %e, "Failed to serialize mirrord config");
         McpError::internal_error("Failed to serialize mirrord config".to_string(), None)
     })?;
 
-    // Create temporary project directory
-    let project_dir = format!("/tmp/mirrord_agent_code_{}", Uuid::new_v4());
-    tracing::debug!("Creating project directory: {}", project_dir);
+    // Persistent build workspace: a single, stable Cargo project reused across calls so
+    // dependency crates stay compiled, plus a cache of binaries keyed by a hash of `code`
+    // so an unchanged script never needs to be rebuilt at all.
+    let cache_root = std::env::var("HOME")
+        .map(|home| format!("{}/.cache/mirrord-mcp", home))
+        .unwrap_or_else(|_| "/tmp/mirrord-mcp-cache".to_string());
+    let project_dir = format!("{}/agent-project", cache_root);
+    let binaries_dir = format!("{}/binaries", cache_root);
     std::fs::create_dir_all(format!("{}/src", &project_dir)).map_err(|e| {
         tracing::error!(error=%e, "Failed to create project directory");
         McpError::internal_error("Failed to create project directory".to_string(), None)
     })?;
+    std::fs::create_dir_all(&binaries_dir).map_err(|e| {
+        tracing::error!(error=%e, "Failed to create binary cache directory");
+        McpError::internal_error("Failed to create binary cache directory".to_string(), None)
+    })?;
 
     let compile_mode = std::env::var("MCP_SERVICE_COMPILE_MODE").unwrap_or("release".to_string());
     tracing::debug!("Compile mode: {}", compile_mode);
 
-    // Write Cargo.toml
-    let mut cargo_toml = r#"
+    let code_hash = {
+        let mut hasher = Sha256::new();
+        hasher.update(request.code.as_bytes());
+        format!("{:x}", hasher.finalize())
+    };
+    let cached_binary_path = format!("{}/{}-{}", binaries_dir, code_hash, compile_mode);
+    let force_clean_build = std::env::var("MCP_SERVICE_FORCE_CLEAN_BUILD").is_ok();
+
+    // Hold the build lock for the whole check-then-build sequence below: otherwise two
+    // overlapping calls for different `code` can race on the shared `project_dir` (one
+    // overwriting main.rs mid-compile of the other) and a binary compiled from one
+    // caller's code could get cached and served under a different caller's hash.
+    let build_guard = BUILD_LOCK.lock().await;
+    let binary_path = if !force_clean_build && Path::new(&cached_binary_path).exists() {
+        tracing::info!(
+            "Reusing cached binary for code hash {} ({})",
+            code_hash,
+            cached_binary_path
+        );
+        cached_binary_path
+    } else {
+        if force_clean_build {
+            tracing::info!("MCP_SERVICE_FORCE_CLEAN_BUILD set, forcing a clean rebuild");
+            let _ = std::fs::remove_dir_all(format!("{}/target", &project_dir));
+        }
+
+        // Write Cargo.toml. The dependency set is fixed, so this only needs to be
+        // (re)written once per project directory, but writing it on every build is cheap and
+        // keeps this self-healing if the cache dir was tampered with.
+        let mut cargo_toml = r#"
 [package]
 name = "agent-code"
 version = "0.1.0"
@@ -157,11 +292,11 @@ serde = { version = "1.0", features = ["derive"] }
 serde_json = "1.0"
 anyhow = "1.0"
 "#
-    .to_string();
+        .to_string();
 
-    if compile_mode == "debug" {
-        cargo_toml.push_str(
-            r#"
+        if compile_mode == "debug" {
+            cargo_toml.push_str(
+                r#"
 [profile.dev]
 opt-level = 1
 debug = false
@@ -173,59 +308,78 @@ panic = "abort"
 incremental = true
 codegen-units = 16
 "#,
-        );
-    }
+            );
+        }
 
-    std::fs::write(format!("{}/Cargo.toml", &project_dir), cargo_toml).map_err(|e| {
-        tracing::error!(error = %e, "Failed to write Cargo.toml");
-        McpError::internal_error("Failed to write Cargo.toml".to_string(), None)
-    })?;
-    tracing::debug!("Wrote Cargo.toml to {}", project_dir);
+        std::fs::write(format!("{}/Cargo.toml", &project_dir), cargo_toml).map_err(|e| {
+            tracing::error!(error = %e, "Failed to write Cargo.toml");
+            McpError::internal_error("Failed to write Cargo.toml".to_string(), None)
+        })?;
+        tracing::debug!("Wrote Cargo.toml to {}", project_dir);
 
-    // Write main.rs
-    std::fs::write(format!("{}/src/main.rs", &project_dir), &request.code).map_err(|e| {
-        tracing::error!(error = %e, "Failed to write main.rs");
-        McpError::internal_error("Failed to write main.rs".to_string(), None)
-    })?;
-    tracing::debug!(
-        "Wrote main.rs with code length: {} bytes",
-        request.code.len()
-    );
-
-    // Compile
-    tracing::info!("Compiling Rust code in {}", project_dir);
-    let compile_args = match compile_mode.as_str() {
-        "debug" => &["build"][..],
-        _ => &["build", "--release"][..],
-    };
-    let compile_output = Command::new("cargo")
-        .current_dir(&project_dir)
-        .args(compile_args)
-        .output()
-        .map_err(|e| {
-            tracing::error!(error = %e, "Failed to execute cargo build");
-            McpError::internal_error("Failed to execute cargo build".to_string(), None)
+        // Write main.rs
+        std::fs::write(format!("{}/src/main.rs", &project_dir), &request.code).map_err(|e| {
+            tracing::error!(error = %e, "Failed to write main.rs");
+            McpError::internal_error("Failed to write main.rs".to_string(), None)
         })?;
+        tracing::debug!(
+            "Wrote main.rs with code length: {} bytes",
+            request.code.len()
+        );
 
-    if !compile_output.status.success() {
-        let err = String::from_utf8_lossy(&compile_output.stderr);
-        tracing::error!(error = %err, "Build failed");
-        return Err(McpError::internal_error(
-            format!("Build failed: {}", err),
-            None,
-        ));
-    }
-    tracing::info!("Compilation succeeded");
+        // Compile. Reusing `project_dir`/target across calls means only main.rs needs to
+        // be rebuilt incrementally; reqwest/serde etc stay compiled from the last run.
+        tracing::info!("Compiling Rust code in {}", project_dir);
+        let compile_args = match compile_mode.as_str() {
+            "debug" => vec!["build", "--message-format=json-diagnostic-rendered-ansi"],
+            _ => vec![
+                "build",
+                "--release",
+                "--message-format=json-diagnostic-rendered-ansi",
+            ],
+        };
+        let mut compile_command = Command::new("cargo");
+        compile_command.current_dir(&project_dir).args(&compile_args);
+        let (compile_status, compile_stdout, compile_stderr) =
+            stream_child_output(compile_command, "cargo build", None).await?;
 
-    let binary_path = format!("{}/target/{}/agent-code", &project_dir, &compile_mode);
-    if !Path::new(&binary_path).exists() {
-        tracing::error!("Binary not found at: {}", binary_path);
-        return Err(McpError::internal_error(
-            format!("Binary not found at: {}", binary_path),
-            None,
-        ));
-    }
-    tracing::debug!("Binary created at {}", binary_path);
+        if !compile_status.success() {
+            let diagnostics = parse_compiler_diagnostics(compile_stdout.as_bytes());
+            let err = if diagnostics.is_empty() {
+                // No JSON diagnostics emitted (e.g. a linker error) - fall back to raw stderr.
+                compile_stderr
+            } else {
+                serde_json::to_string(&diagnostics).unwrap_or_default()
+            };
+            tracing::error!(error = %err, "Build failed");
+            return Err(McpError::internal_error(
+                format!("Build failed: {}", err),
+                None,
+            ));
+        }
+        tracing::info!("Compilation succeeded");
+
+        let built_binary_path = format!("{}/target/{}/agent-code", &project_dir, &compile_mode);
+        if !Path::new(&built_binary_path).exists() {
+            tracing::error!("Binary not found at: {}", built_binary_path);
+            return Err(McpError::internal_error(
+                format!("Binary not found at: {}", built_binary_path),
+                None,
+            ));
+        }
+
+        std::fs::copy(&built_binary_path, &cached_binary_path).map_err(|e| {
+            tracing::error!(error = %e, "Failed to cache compiled binary");
+            McpError::internal_error("Failed to cache compiled binary".to_string(), None)
+        })?;
+        tracing::debug!(
+            "Binary created at {} and cached at {}",
+            built_binary_path,
+            cached_binary_path
+        );
+        cached_binary_path
+    };
+    drop(build_guard);
 
     // Write mirrord config to temp file
     let mut config_file = NamedTempFile::with_suffix(".json").map_err(|e| {
@@ -247,31 +401,26 @@ codegen-units = 16
     tracing::debug!("Wrote mirrord config to {}", config_path);
 
     // Run mirrord
-    tracing::info!("Executing mirrord for pod: {}", pod_name);
-    let output = Command::new("mirrord")
+    tracing::info!("Executing mirrord with config: {}", config_str);
+    let mut mirrord_command = Command::new("mirrord");
+    mirrord_command
         .arg("exec")
         .arg("--config-file")
         .arg(&config_path)
-        .arg(&binary_path)
-        .output()
-        .map_err(|e| {
-            tracing::error!(error = %e, "Failed to execute mirrord");
-            McpError::internal_error("Failed to execute mirrord".to_string(), None)
-        })?;
+        .arg(&binary_path);
+    let (status, stdout, stderr) =
+        stream_child_output(mirrord_command, "mirrord exec", None).await?;
 
-    // Clean up
-    let _ = std::fs::remove_dir_all(&project_dir);
+    // Clean up. `project_dir` and the binary cache are persistent across calls, so only
+    // the per-request config file needs removing.
     let _ = config_file.close();
-    tracing::debug!("Cleaned up project directory and config file");
+    tracing::debug!("Cleaned up config file");
 
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    if status.success() {
         tracing::info!("Mirrord execution succeeded");
         tracing::debug!("stdout: '{}', stderr: '{}'", stdout, stderr);
         Ok(stdout)
     } else {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
         tracing::error!(error = stderr, "Mirrord execution failed");
         tracing::debug!("Mirrord config used: {}", config_str);
         Err(McpError::internal_error(
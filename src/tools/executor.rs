@@ -1,14 +1,118 @@
 use anyhow::Result;
 use rmcp::Error as McpError;
+use serde_json::json;
 use std::io::Write;
-use std::process::Command;
+use std::process::{ExitStatus, Stdio};
 use std::time::Duration;
 use tempfile::{NamedTempFile, TempPath}; // Use TempPath for config file persistence
-use tokio::task;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
 use tokio::time::timeout;
-use crate::tools::utils::update_mirrord_config;
+use uuid::Uuid;
+use crate::tools::runnable::MirrordRunnable;
+use crate::tools::utils::{KUBECTL_TIMEOUT, TargetKind, update_mirrord_config};
 
-const MIRRORD_EXEC_TIMEOUT: Duration = Duration::from_secs(120); // 2 minutes
+/// Default mirrord exec timeout, used when a caller doesn't override it via a request field.
+pub const MIRRORD_EXEC_TIMEOUT: Duration = Duration::from_secs(120); // 2 minutes
+
+/// Spawns `command` with piped stdio and streams its stdout/stderr line-by-line to
+/// `tracing` as they arrive, while also accumulating the full output for the caller.
+/// The live lines go to the server's logs only; the caller receives the accumulated
+/// output once the child exits.
+/// When `stdin_data` is `Some`, it's written to the child's standard input and the
+/// handle is then closed so the child sees EOF; otherwise stdin is left closed (`null`).
+pub async fn stream_child_output(
+    mut command: Command,
+    label: &str,
+    stdin_data: Option<&[u8]>,
+) -> Result<(ExitStatus, String, String), McpError> {
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    command.stdin(if stdin_data.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    });
+
+    let mut child = command.spawn().map_err(|e| {
+        tracing::error!(error = %e, label, "Failed to spawn child process");
+        if e.kind() == std::io::ErrorKind::NotFound {
+            McpError::internal_error(format!("{}: command not found in PATH", label), None)
+        } else {
+            McpError::internal_error(format!("Failed to spawn {}: {}", label, e), None)
+        }
+    })?;
+
+    // Write stdin concurrently with the stdout/stderr readers below, not before them: a
+    // child that produces enough output to fill its stdout/stderr pipe before it's done
+    // reading stdin would otherwise block on the full, undrained pipe while nothing reads
+    // it, deadlocking the write_all().await with the reader tasks never even spawned.
+    let stdin_task = stdin_data.map(|data| {
+        let data = data.to_vec();
+        let mut stdin = child.stdin.take().expect("stdin was piped");
+        let stdin_label = label.to_string();
+        tokio::spawn(async move {
+            let result = stdin.write_all(&data).await;
+            drop(stdin); // close stdin so the child sees EOF
+            result.map_err(|e| {
+                tracing::error!(error = %e, label = %stdin_label, "Failed to write to child stdin");
+                McpError::internal_error(
+                    format!("Failed to write to {} stdin: {}", stdin_label, e),
+                    None,
+                )
+            })
+        })
+    });
+
+    let stdout = child.stdout.take().expect("stdout was piped");
+    let stderr = child.stderr.take().expect("stderr was piped");
+    let stdout_label = label.to_string();
+    let stderr_label = label.to_string();
+
+    let stdout_task = tokio::spawn(async move {
+        let mut lines = BufReader::new(stdout).lines();
+        let mut acc = String::new();
+        while let Ok(Some(line)) = lines.next_line().await {
+            tracing::info!(stream = "stdout", label = %stdout_label, "{}", line);
+            acc.push_str(&line);
+            acc.push('\n');
+        }
+        acc
+    });
+    let stderr_task = tokio::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        let mut acc = String::new();
+        while let Ok(Some(line)) = lines.next_line().await {
+            tracing::info!(stream = "stderr", label = %stderr_label, "{}", line);
+            acc.push_str(&line);
+            acc.push('\n');
+        }
+        acc
+    });
+
+    let status = child.wait().await.map_err(|e| {
+        tracing::error!(error = %e, label, "Failed to wait on child process");
+        McpError::internal_error(format!("Failed to wait on {}: {}", label, e), None)
+    })?;
+
+    if let Some(stdin_task) = stdin_task {
+        stdin_task
+            .await
+            .map_err(|e| {
+                McpError::internal_error(
+                    format!("stdin writer task for {} panicked: {}", label, e),
+                    None,
+                )
+            })??;
+    }
+    let stdout = stdout_task.await.map_err(|e| {
+        McpError::internal_error(format!("stdout reader task for {} panicked: {}", label, e), None)
+    })?;
+    let stderr = stderr_task.await.map_err(|e| {
+        McpError::internal_error(format!("stderr reader task for {} panicked: {}", label, e), None)
+    })?;
+
+    Ok((status, stdout, stderr))
+}
 
 /// Executes a language-specific script/binary using mirrord.
 ///
@@ -21,25 +125,36 @@ const MIRRORD_EXEC_TIMEOUT: Duration = Duration::from_secs(120); // 2 minutes
 /// * `deployment` - The target Kubernetes deployment name.
 /// * `mirrord_config` - The base mirrord configuration (JSON string).
 /// * `namespace` - The target Kubernetes namespace (currently hardcoded, consider making configurable).
+/// * `stdin` - Optional bytes written to the executed process's standard input before it's closed.
 ///
 /// # Returns
 /// The stdout of the successful execution, or an McpError.
 pub async fn execute_mirrord_run(
     cmd_str: &str,
-    deployment: &str,
+    deployment: Option<&str>,
     mirrord_config: &str,
     namespace: &str,
+    stdin: Option<&str>,
 ) -> Result<String, McpError> {
     let args = shell_words::split(cmd_str).map_err(|e| {
         tracing::error!(error = %e, "Failed to parse command line arguments");
         McpError::internal_error("Failed to parse command line arguments".to_string(), None)
     })?;
     // --- 1. Update and Write Mirrord Config ---
-    let config_str = update_mirrord_config(mirrord_config, deployment, namespace)
-        .await
-        .inspect_err(|e| {
-            tracing::error!(error = ?e, "Failed to update mirrord config");
-        })?;
+    let config_str = update_mirrord_config(
+        mirrord_config,
+        deployment,
+        namespace,
+        KUBECTL_TIMEOUT,
+        TargetKind::Pod,
+        None,
+        None,
+        None,
+    )
+    .await
+    .inspect_err(|e| {
+        tracing::error!(error = ?e, "Failed to update mirrord config");
+    })?;
 
     let mut config_file = NamedTempFile::with_suffix(".json").map_err(|e| {
         tracing::error!(error = %e, "Failed to create temp config file");
@@ -55,80 +170,50 @@ pub async fn execute_mirrord_run(
     tracing::debug!("Wrote mirrord config to {}", config_path.display());
 
     // --- 5. Execute Mirrord ---
-    let config_path_owned = config_path.to_path_buf(); // Clone PathBuf to move into task
-    let blocking_task = task::spawn_blocking(move || {
-        let mut command = Command::new("mirrord");
-        command
-            .arg("exec")
-            .arg("--config-file")
-            .arg(&config_path_owned); // Use owned path
-        for arg in args {
-            command.arg(arg);
-        }
-        tracing::info!(command = ?command, "Executing mirrord command in blocking task...");
-        command.output() // Execute the command
-    });
+    let mut command = Command::new("mirrord");
+    command.arg("exec").arg("--config-file").arg(&config_path);
+    for arg in &args {
+        command.arg(arg);
+    }
+    tracing::info!(command = ?command, "Executing mirrord command, streaming output...");
 
-    let output = match timeout(MIRRORD_EXEC_TIMEOUT, blocking_task).await {
-        Ok(Ok(Ok(output))) => Ok(output), // All succeeded
-        Ok(Ok(Err(e))) => {
-            // Command::output failed
-            tracing::error!(error = %e, "Failed to run mirrord command");
-            if e.kind() == std::io::ErrorKind::NotFound {
-                Err(McpError::internal_error(
-                    "Failed to execute mirrord: 'mirrord' command not found in PATH.".to_string(),
-                    None,
-                ))
-            } else {
-                Err(McpError::internal_error(
-                    format!("Failed to start mirrord process: {}", e),
-                    None,
-                ))
-            }
-        }
-        Ok(Err(e)) => {
-            // spawn_blocking failed
-            tracing::error!(error = %e, "mirrord blocking task failed");
-            Err(McpError::internal_error(
-                format!("mirrord task failed: {}", e),
-                None,
-            ))
-        }
+    let run = timeout(
+        MIRRORD_EXEC_TIMEOUT,
+        stream_child_output(command, "mirrord exec", stdin.map(str::as_bytes)),
+    )
+    .await;
+
+    let (status, stdout, stderr) = match run {
+        Ok(result) => result?,
         Err(_) => {
-            // Timeout elapsed
             tracing::error!(
                 "Mirrord execution timed out after {:?}",
                 MIRRORD_EXEC_TIMEOUT
             );
-            Err(McpError::internal_error(
+            return Err(McpError::internal_error(
                 format!(
                     "Mirrord execution timed out after {:?}",
                     MIRRORD_EXEC_TIMEOUT
                 ),
                 None,
-            ))
+            ));
         }
-    }?;
+    };
 
     // --- 6. Handle Output ---
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    if status.success() {
         tracing::info!("Mirrord execution succeeded");
         tracing::debug!(
             "stdout num bytes: {}, stderr num bytes: {}",
             stdout.len(),
             stderr.len()
         );
-        tracing::trace!("stdout: '{}', stderr: '{}'", stdout, stderr);
         Ok(stdout)
     } else {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        let exit_code_info = output
-            .status
+        let exit_code_info = status
             .code()
             .map_or_else(|| "None".to_string(), |c| c.to_string());
-        tracing::error!(exit_code = %exit_code_info, error = stderr, "Mirrord execution failed");
+        tracing::error!(exit_code = %exit_code_info, error = %stderr, "Mirrord execution failed");
         tracing::debug!("Mirrord config used: {}", config_str);
         Err(McpError::internal_error(
             format!(
@@ -140,3 +225,147 @@ pub async fn execute_mirrord_run(
     }
     // --- 7. Cleanup --- (Automatic via Drop)
 }
+
+/// Runs a `MirrordRunnable` against mirrord, or, when `dry_run` is set, resolves everything
+/// it would do (target, merged config, project files, command) and returns that plan as a
+/// JSON string without setting up the project directory or invoking mirrord (a runnable's
+/// `dry_run_check` may still run its own checks in a scratch directory). Mirrors cargo's
+/// `--build-plan`: validate target resolution and command construction before mutating
+/// cluster traffic.
+pub async fn execute_mirrord_runnable<R: MirrordRunnable>(
+    runner: &R,
+    deployment: Option<&str>,
+    mirrord_config: &str,
+    namespace: &str,
+    dry_run: bool,
+    kubectl_timeout: Duration,
+    exec_timeout: Duration,
+    target_kind: TargetKind,
+    container: Option<&str>,
+    aws_profile: Option<&str>,
+    aws_region: Option<&str>,
+) -> Result<String, McpError> {
+    let config_str = update_mirrord_config(
+        mirrord_config,
+        deployment,
+        namespace,
+        kubectl_timeout,
+        target_kind,
+        container,
+        aws_profile,
+        aws_region,
+    )
+    .await
+    .inspect_err(|e| {
+        tracing::error!(error = ?e, "Failed to update mirrord config");
+    })?;
+
+    let project_dir = std::env::temp_dir().join(format!("mirrord_agent_code_{}", Uuid::new_v4()));
+
+    if dry_run {
+        let command_args = runner.get_command_args(&project_dir)?;
+        let files: serde_json::Map<String, serde_json::Value> = runner
+            .describe_files()
+            .into_iter()
+            .map(|(path, contents)| (path, json!(contents)))
+            .collect();
+        let mut mirrord_invocation = vec!["mirrord".to_string(), "exec".to_string(), "--config-file".to_string(), "<generated-config>.json".to_string()];
+        mirrord_invocation.extend(command_args.iter().map(|a| a.to_string_lossy().into_owned()));
+        let validation = runner.dry_run_check(&project_dir).await?;
+
+        let mut plan = json!({
+            "dry_run": true,
+            "deployment": deployment,
+            "namespace": namespace,
+            "resolved_mirrord_config": serde_json::from_str::<serde_json::Value>(&config_str).unwrap_or(json!(config_str)),
+            "project_dir": project_dir.display().to_string(),
+            "files": files,
+            "command_args": command_args.iter().map(|a| a.to_string_lossy().into_owned()).collect::<Vec<_>>(),
+            "mirrord_invocation": mirrord_invocation,
+        });
+        if let Some(validation) = validation {
+            plan["validation"] = validation;
+        }
+        return serde_json::to_string_pretty(&plan).map_err(|e| {
+            tracing::error!(error = %e, "Failed to serialize dry-run plan");
+            McpError::internal_error("Failed to serialize dry-run plan".to_string(), None)
+        });
+    }
+
+    std::fs::create_dir_all(&project_dir).map_err(|e| {
+        tracing::error!(error = %e, path = %project_dir.display(), "Failed to create project directory");
+        McpError::internal_error("Failed to create project directory".to_string(), None)
+    })?;
+
+    runner.setup_project(&project_dir).await?;
+    let command_args = runner.get_command_args(&project_dir)?;
+
+    let mut config_file = NamedTempFile::with_suffix(".json").map_err(|e| {
+        tracing::error!(error = %e, "Failed to create temp config file");
+        McpError::internal_error("Failed to create temp config file".to_string(), None)
+    })?;
+    config_file.write_all(config_str.as_bytes()).map_err(|e| {
+        tracing::error!(error = %e, "Failed to write mirrord config");
+        McpError::internal_error("Failed to write mirrord config".to_string(), None)
+    })?;
+    let config_path: TempPath = config_file.into_temp_path();
+
+    let mut command = Command::new("mirrord");
+    command.arg("exec").arg("--config-file").arg(&config_path);
+    for arg in &command_args {
+        command.arg(arg);
+    }
+
+    // Resolve credentials unconditionally: `aws_profile`/`aws_region` are pure overrides to
+    // the default provider chain, so the common case (EKS via IRSA/ambient env credentials,
+    // no explicit profile/region) needs this too, not just when a caller passes one. But
+    // plenty of targets (local/non-AWS clusters) have no AWS credentials at all, so failure
+    // to resolve is soft: log and skip injecting the extra AWS_* env vars rather than
+    // aborting a request that never needed them in the first place.
+    match crate::eks::resolve_credential_env(aws_profile, aws_region, kubectl_timeout).await {
+        Ok(credential_env) => {
+            command.envs(credential_env);
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to resolve AWS credentials, continuing without them");
+        }
+    }
+
+    tracing::info!(command = ?command, "Executing mirrord command, streaming output...");
+
+    let run = timeout(
+        exec_timeout,
+        stream_child_output(command, "mirrord exec", None),
+    )
+    .await;
+
+    let _ = std::fs::remove_dir_all(&project_dir);
+
+    let (status, stdout, stderr) = match run {
+        Ok(result) => result?,
+        Err(_) => {
+            tracing::error!("Mirrord execution timed out after {:?}", exec_timeout);
+            return Err(McpError::internal_error(
+                format!("Mirrord execution timed out after {:?}", exec_timeout),
+                None,
+            ));
+        }
+    };
+
+    if status.success() {
+        tracing::info!("Mirrord execution succeeded");
+        Ok(stdout)
+    } else {
+        let exit_code_info = status
+            .code()
+            .map_or_else(|| "None".to_string(), |c| c.to_string());
+        tracing::error!(exit_code = %exit_code_info, error = %stderr, "Mirrord execution failed");
+        Err(McpError::internal_error(
+            format!(
+                "Mirrord execution failed (Exit Code: {}): {}",
+                exit_code_info, stderr
+            ),
+            None,
+        ))
+    }
+}
@@ -1,13 +1,13 @@
-use super::executor::execute_mirrord_run;
+use super::executor::{MIRRORD_EXEC_TIMEOUT, execute_mirrord_runnable, stream_child_output};
 use super::runnable::MirrordRunnable;
+use super::utils::{KUBECTL_TIMEOUT, TargetKind};
 use anyhow::Result;
 use rmcp::Error as McpError;
 use rmcp::schemars;
 use std::ffi::OsString;
 use std::path::Path;
-use std::process::Command;
 use std::time::Duration;
-use tokio::task;
+use tokio::process::Command;
 use tokio::time::timeout;
 
 const NPM_INSTALL_TIMEOUT: Duration = Duration::from_secs(180);
@@ -24,10 +24,25 @@ pub struct Request {
         description = "Mirrord config in JSON format, e.g., '{\"feature\": {\"network\": {\"incoming\": {\"mode\": \"mirror\", \"ports\": [8888]}}}}'."
     )]
     mirrord_config: String,
+    #[schemars(
+        description = "When true, resolve the target, merged mirrord config, project files and command without writing anything to disk or running mirrord, and return the plan as JSON."
+    )]
+    #[serde(default)]
+    dry_run: bool,
+    #[schemars(
+        description = "Overrides all operation timeouts (npm install, pod resolution, mirrord exec) for this call, as a humantime string (e.g. \"5m\", \"90s\"). Defaults to the server's built-in timeouts when unset."
+    )]
+    timeout: Option<String>,
+    #[schemars(description = "The kind of workload `deployment` names: pod, deployment, statefulset, rollout, or service. Defaults to pod.")]
+    #[serde(default)]
+    target_kind: TargetKind,
+    #[schemars(description = "Optional container name to scope the mirrord target to within the resolved pod/deployment/statefulset.")]
+    container: Option<String>,
 }
 
 struct NodeRunner<'a> {
     code: &'a str,
+    install_timeout: Duration,
 }
 
 impl MirrordRunnable for NodeRunner<'_> {
@@ -61,52 +76,30 @@ impl MirrordRunnable for NodeRunner<'_> {
             self.code.len()
         );
 
-        // Install dependencies
+        // Install dependencies, streaming npm's output live instead of buffering it
         tracing::info!(
             "Installing Node.js dependencies in {}",
             project_dir.display()
         );
-        let project_dir_owned = project_dir.to_path_buf(); // Clone for task
-        let blocking_task = task::spawn_blocking(move || {
-            Command::new("npm")
-                .current_dir(&project_dir_owned) // Use owned path
-                .arg("install")
-                .output()
-        });
-        let npm_install_output = match timeout(NPM_INSTALL_TIMEOUT, blocking_task).await {
-            Ok(Ok(Ok(output))) => output,
-            Ok(Ok(Err(e))) => {
-                tracing::error!(error = %e, "Failed to execute npm install");
-                return if e.kind() == std::io::ErrorKind::NotFound {
-                    Err(McpError::internal_error(
-                        "Failed to run npm: 'npm' command not found in PATH.".to_string(),
-                        None,
-                    ))
-                } else {
-                    Err(McpError::internal_error(
-                        format!("Failed to start npm process: {}", e),
-                        None,
-                    ))
-                };
-            }
-            Ok(Err(e)) => {
-                tracing::error!(error = %e, "npm install blocking task failed");
-                return Err(McpError::internal_error(
-                    format!("npm install task failed: {}", e),
-                    None,
-                ));
-            }
+        let mut command = Command::new("npm");
+        command.current_dir(project_dir).arg("install");
+
+        let (status, stdout, stderr) = match timeout(
+            self.install_timeout,
+            stream_child_output(command, "npm install", None),
+        )
+        .await
+        {
+            Ok(result) => result?,
             Err(_) => {
-                tracing::error!("npm install timed out after {:?}", NPM_INSTALL_TIMEOUT);
+                tracing::error!("npm install timed out after {:?}", self.install_timeout);
                 return Err(McpError::internal_error(
-                    format!("npm install timed out after {:?}", NPM_INSTALL_TIMEOUT),
+                    format!("npm install timed out after {:?}", self.install_timeout),
                     None,
                 ));
             }
         };
-        if !npm_install_output.status.success() {
-            let stderr = String::from_utf8_lossy(&npm_install_output.stderr);
-            let stdout = String::from_utf8_lossy(&npm_install_output.stdout);
+        if !status.success() {
             tracing::error!(stderr = %stderr, stdout = %stdout, "npm install failed");
             return Err(McpError::internal_error(
                 format!("npm install failed: {}", stderr), // Primarily report stderr
@@ -124,18 +117,56 @@ impl MirrordRunnable for NodeRunner<'_> {
             script_path.into(),     // The script path as an OsString
         ])
     }
+
+    fn describe_files(&self) -> Vec<(String, String)> {
+        vec![
+            (
+                "package.json".to_string(),
+                r#"{
+  "name": "mirrord-node-code",
+  "version": "0.1.0",
+  "dependencies": {
+    "axios": "^1.7.0"
+  }
+}
+"#
+                .to_string(),
+            ),
+            ("index.js".to_string(), self.code.to_string()),
+        ]
+    }
 }
 
 pub async fn run(request: Request) -> Result<String, McpError> {
+    let timeout_override = parse_timeout(request.timeout.as_deref())?;
     let runner = NodeRunner {
         code: &request.code,
+        install_timeout: timeout_override.unwrap_or(NPM_INSTALL_TIMEOUT),
     };
 
-    execute_mirrord_run(
+    execute_mirrord_runnable(
         &runner,
-        &request.deployment,
+        Some(&request.deployment),
         &request.mirrord_config,
         "default",
+        request.dry_run,
+        timeout_override.unwrap_or(KUBECTL_TIMEOUT),
+        timeout_override.unwrap_or(MIRRORD_EXEC_TIMEOUT),
+        request.target_kind,
+        request.container.as_deref(),
+        None,
+        None,
     )
     .await
 }
+
+fn parse_timeout(timeout_str: Option<&str>) -> Result<Option<Duration>, McpError> {
+    timeout_str
+        .map(|s| {
+            humantime::parse_duration(s).map_err(|e| {
+                tracing::error!(error = %e, timeout = s, "Failed to parse timeout");
+                McpError::invalid_params(format!("Invalid timeout '{}': {}", s, e), None)
+            })
+        })
+        .transpose()
+}
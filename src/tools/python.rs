@@ -1,15 +1,23 @@
-use super::executor::execute_mirrord_run;
+use super::executor::{execute_mirrord_runnable, stream_child_output};
 use super::runnable::MirrordRunnable;
+use super::utils::TargetKind;
 use anyhow::Result;
 use rmcp::Error as McpError;
 use rmcp::schemars;
+use serde_json::json;
 use std::ffi::OsString; // Use OsString for command args
 use std::path::Path;
 use std::time::Duration;
+use tokio::process::Command;
 use tokio::task;
 use tokio::time::timeout;
 
 const PYTHON_INSTALL_TIMEOUT: Duration = Duration::from_secs(180);
+const PYTHON_DRY_RUN_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn default_namespace() -> String {
+    "default".to_string()
+}
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct Request {
@@ -17,12 +25,31 @@ pub struct Request {
         description = "Complete Python code using only requests for HTTP requests and json for deserialization. The resulting script is run against the cluster."
     )]
     code: String,
-    #[schemars(description = "Kubernetes deployment name.")]
-    deployment: String,
+    #[schemars(
+        description = "Kubernetes deployment name. When omitted, mirrord runs targetless: an independent agent with cluster network/DNS access but no mirrored pod."
+    )]
+    deployment: Option<String>,
     #[schemars(
         description = "Mirrord config in JSON format, e.g., '{\"feature\": {\"network\": {\"incoming\": {\"mode\": \"mirror\", \"ports\": [8888]}}}}'."
     )]
     mirrord_config: String,
+    #[schemars(
+        description = "When true, resolve the target, merged mirrord config, project files and command without setting up the project or running mirrord, syntax-check the code with py_compile and resolve dependencies with pip install --dry-run in a temporary directory, and return the plan (with those check results under \"validation\") as JSON."
+    )]
+    #[serde(default)]
+    dry_run: bool,
+    #[schemars(description = "Kubernetes namespace the target lives in. Defaults to \"default\".")]
+    #[serde(default = "default_namespace")]
+    namespace: String,
+    #[schemars(description = "The kind of workload `deployment` names: pod, deployment, statefulset, rollout, or service. Defaults to pod.")]
+    #[serde(default)]
+    target_kind: TargetKind,
+    #[schemars(
+        description = "Named AWS profile to resolve credentials from for clusters using exec-based (e.g. EKS) auth. Defaults to the standard AWS provider chain."
+    )]
+    aws_profile: Option<String>,
+    #[schemars(description = "AWS region to resolve credentials for. Defaults to the standard AWS provider chain.")]
+    aws_region: Option<String>,
 }
 struct PythonRunner<'a> {
     code: &'a str,
@@ -160,6 +187,108 @@ impl MirrordRunnable for PythonRunner<'_> {
             script_path.into(),
         ])
     }
+
+    fn describe_files(&self) -> Vec<(String, String)> {
+        vec![
+            ("main.py".to_string(), self.code.to_string()),
+            ("requirements.txt".to_string(), "requests\n".to_string()),
+        ]
+    }
+
+    /// Dry-run equivalent of `setup_project`'s venv + pip install: a `py_compile` syntax
+    /// check on `main.py` and a `pip install --dry-run` dependency resolution check, both
+    /// against a scratch directory that's removed before returning, so dry-run callers get
+    /// real validation instead of just an echo of the files that would be written.
+    async fn dry_run_check(
+        &self,
+        _project_dir: &Path,
+    ) -> Result<Option<serde_json::Value>, McpError> {
+        let scratch_dir = tempfile::tempdir().map_err(|e| {
+            tracing::error!(error = %e, "Failed to create dry-run scratch directory");
+            McpError::internal_error(
+                "Failed to create dry-run scratch directory".to_string(),
+                None,
+            )
+        })?;
+        let main_py_path = scratch_dir.path().join("main.py");
+        std::fs::write(&main_py_path, self.code).map_err(|e| {
+            tracing::error!(error = %e, path = %main_py_path.display(), "Failed to write main.py for dry run");
+            McpError::internal_error("Failed to write main.py for dry run".to_string(), None)
+        })?;
+
+        let mut py_compile_command = Command::new("python3");
+        py_compile_command.arg("-m").arg("py_compile").arg(&main_py_path);
+        let (py_compile_status, _stdout, py_compile_stderr) = match timeout(
+            PYTHON_DRY_RUN_TIMEOUT,
+            stream_child_output(py_compile_command, "py_compile", None),
+        )
+        .await
+        {
+            Ok(result) => result?,
+            Err(_) => {
+                tracing::error!(
+                    "py_compile dry run timed out after {:?}",
+                    PYTHON_DRY_RUN_TIMEOUT
+                );
+                return Err(McpError::internal_error(
+                    format!(
+                        "py_compile dry run timed out after {:?}",
+                        PYTHON_DRY_RUN_TIMEOUT
+                    ),
+                    None,
+                ));
+            }
+        };
+
+        let requirements = "requests\n";
+        let req_path = scratch_dir.path().join("requirements.txt");
+        std::fs::write(&req_path, requirements).map_err(|e| {
+            tracing::error!(error = %e, path = %req_path.display(), "Failed to write requirements.txt for dry run");
+            McpError::internal_error(
+                "Failed to write requirements.txt for dry run".to_string(),
+                None,
+            )
+        })?;
+
+        let mut pip_command = Command::new("pip");
+        pip_command
+            .arg("install")
+            .arg("--dry-run")
+            .arg("-r")
+            .arg(&req_path);
+        let (pip_status, _stdout, pip_stderr) = match timeout(
+            PYTHON_DRY_RUN_TIMEOUT,
+            stream_child_output(pip_command, "pip install --dry-run", None),
+        )
+        .await
+        {
+            Ok(result) => result?,
+            Err(_) => {
+                tracing::error!(
+                    "pip install --dry-run timed out after {:?}",
+                    PYTHON_DRY_RUN_TIMEOUT
+                );
+                return Err(McpError::internal_error(
+                    format!(
+                        "pip install --dry-run timed out after {:?}",
+                        PYTHON_DRY_RUN_TIMEOUT
+                    ),
+                    None,
+                ));
+            }
+        };
+
+        Ok(Some(json!({
+            "py_compile": {
+                "success": py_compile_status.success(),
+                "stderr": py_compile_stderr,
+            },
+            "pip_install_dry_run": {
+                "success": pip_status.success(),
+                "stderr": pip_stderr,
+            },
+        })))
+    }
 }
 
 pub async fn run(request: Request) -> Result<String, McpError> {
@@ -167,11 +296,18 @@ pub async fn run(request: Request) -> Result<String, McpError> {
         code: &request.code,
     };
 
-    execute_mirrord_run(
+    execute_mirrord_runnable(
         &runner,
-        &request.deployment,
+        request.deployment.as_deref(),
         &request.mirrord_config,
-        "default", // Namespace - make configurable later if needed
+        &request.namespace,
+        request.dry_run,
+        super::utils::KUBECTL_TIMEOUT,
+        super::executor::MIRRORD_EXEC_TIMEOUT,
+        request.target_kind,
+        None,
+        request.aws_profile.as_deref(),
+        request.aws_region.as_deref(),
     )
     .await
 }
@@ -13,4 +13,22 @@ pub trait MirrordRunnable {
     /// Gets the command and arguments to execute via `mirrord exec`.
     /// This typically includes the language interpreter/binary and the main script/executable path.
     fn get_command_args(&self, project_dir: &Path) -> Result<Vec<OsString>, McpError>;
+
+    /// Describes the project files `setup_project` would write, as (relative path, contents)
+    /// pairs, without touching disk. Used by dry-run/plan mode to show exactly what would be
+    /// written and compiled.
+    fn describe_files(&self) -> Vec<(String, String)>;
+
+    /// Runs any cheap, non-mutating validation dry-run mode can offer beyond describing files
+    /// (e.g. a syntax check or dependency resolution check), returning JSON to merge into the
+    /// plan under `"validation"`, or `None` if the runnable has nothing extra to check.
+    /// Defaults to a no-op: most runnables have no cheaper check than the real compile/install
+    /// `setup_project` would do.
+    async fn dry_run_check(
+        &self,
+        project_dir: &Path,
+    ) -> Result<Option<serde_json::Value>, McpError> {
+        let _ = project_dir;
+        Ok(None)
+    }
 }
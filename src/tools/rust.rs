@@ -1,13 +1,13 @@
-use super::executor::execute_mirrord_run;
+use super::executor::{MIRRORD_EXEC_TIMEOUT, execute_mirrord_runnable, stream_child_output};
 use super::runnable::MirrordRunnable;
+use super::utils::{KUBECTL_TIMEOUT, TargetKind};
 use anyhow::Result;
 use rmcp::Error as McpError;
 use rmcp::schemars;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 use std::time::Duration;
-use tokio::task;
+use tokio::process::Command;
 use tokio::time::timeout;
 
 const CARGO_BUILD_TIMEOUT: Duration = Duration::from_secs(180);
@@ -24,12 +24,27 @@ pub struct Request {
         description = "Mirrord config in JSON format.e.g., '{\"feature\": {\"network\": {\"incoming\": {\"mode\": \"mirror\", \"ports\": [ 8888 ] } } }'."
     )]
     mirrord_config: String,
+    #[schemars(
+        description = "When true, resolve the target, merged mirrord config, project files and command without writing anything to disk or running mirrord, and return the plan as JSON."
+    )]
+    #[serde(default)]
+    dry_run: bool,
+    #[schemars(
+        description = "Overrides all operation timeouts (cargo build, pod resolution, mirrord exec) for this call, as a humantime string (e.g. \"5m\", \"90s\"). Defaults to the server's built-in timeouts when unset."
+    )]
+    timeout: Option<String>,
+    #[schemars(description = "The kind of workload `deployment` names: pod, deployment, statefulset, rollout, or service. Defaults to pod.")]
+    #[serde(default)]
+    target_kind: TargetKind,
+    #[schemars(description = "Optional container name to scope the mirrord target to within the resolved pod/deployment/statefulset.")]
+    container: Option<String>,
 }
 
 // Struct to hold Rust-specific data and implement the trait
 struct RustRunner<'a> {
     code: &'a str,
     compile_mode: String,
+    build_timeout: Duration,
 }
 
 impl MirrordRunnable for RustRunner<'_> {
@@ -42,37 +57,7 @@ impl MirrordRunnable for RustRunner<'_> {
         })?;
 
         // Write Cargo.toml
-        let mut cargo_toml = r#"
-[package]
-name = "mirrord-agent-code"
-version = "0.1.0"
-edition = "2021" # Consider updating to 2024 if appropriate, but 2021 is safer for broader compiler support
-
-[dependencies]
-reqwest = { version = "0.12", features = ["json", "blocking"] }
-serde = { version = "1.0", features = ["derive"] }
-serde_json = "1.0"
-anyhow = "1.0"
-"#
-        .to_string();
-        // Add debug profile settings if needed
-        if self.compile_mode == "debug" {
-            // Note: These settings are very aggressive in order to minimize compile time
-            cargo_toml.push_str(
-                r#"
-[profile.dev]
-opt-level = 0
-# debug = false # Usually want debug symbols in debug mode
-# split-debuginfo = "unpacked" # Useful for large projects, maybe overkill here
-# debug-assertions = false # Usually want assertions in debug mode
-# overflow-checks = false # Usually want overflow checks in debug mode
-lto = false
-panic = "unwind" # 'abort' can make debugging harder
-incremental = true
-codegen-units = 256 # Default is usually fine
-"#,
-            );
-        }
+        let cargo_toml = self.cargo_toml();
 
         let cargo_toml_path = project_dir.join("Cargo.toml");
         std::fs::write(&cargo_toml_path, cargo_toml).map_err(|e| {
@@ -93,7 +78,7 @@ codegen-units = 256 # Default is usually fine
             self.code.len()
         );
 
-        // Compile
+        // Compile, streaming cargo's output live instead of buffering it
         tracing::info!(
             "Compiling rust cod in {} mode in {}",
             self.compile_mode,
@@ -104,53 +89,26 @@ codegen-units = 256 # Default is usually fine
             _ => &["build", "--release"][..], // Default to release
         };
 
-        let project_dir_owned = project_dir.to_path_buf(); // Clone PathBuf to move into task
-        let blocking_task = task::spawn_blocking(move || {
-            let mut command = Command::new("cargo");
-            command
-                .current_dir(project_dir_owned)
-                .args(compile_args)
-                .output()
-        });
-
-        let compile_output = match timeout(CARGO_BUILD_TIMEOUT, blocking_task).await {
-            Ok(Ok(Ok(output))) => Ok(output), // All succeeded
-            Ok(Ok(Err(e))) => {
-                // Command::output failed
-                tracing::error!(error = %e, "Failed to run cargo build");
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    Err(McpError::internal_error(
-                        "Failed to execute cargo: 'cargo' command not found in PATH.".to_string(),
-                        None,
-                    ))
-                } else {
-                    Err(McpError::internal_error(
-                        format!("Failed to start cargo process: {}", e),
-                        None,
-                    ))
-                }
-            }
-            Ok(Err(e)) => {
-                // spawn_blocking failed
-                tracing::error!(error = %e, "cargo blocking task failed");
-                Err(McpError::internal_error(
-                    format!("cargo task failed: {}", e),
-                    None,
-                ))
-            }
+        let mut command = Command::new("cargo");
+        command.current_dir(project_dir).args(compile_args);
+
+        let (status, stdout, stderr) = match timeout(
+            self.build_timeout,
+            stream_child_output(command, "cargo build", None),
+        )
+        .await
+        {
+            Ok(result) => result?,
             Err(_) => {
-                // Timeout elapsed
-                tracing::error!("Cargo build timed out after {:?}", CARGO_BUILD_TIMEOUT);
-                Err(McpError::internal_error(
-                    format!("Cargo build timed out after {:?}", CARGO_BUILD_TIMEOUT),
+                tracing::error!("Cargo build timed out after {:?}", self.build_timeout);
+                return Err(McpError::internal_error(
+                    format!("Cargo build timed out after {:?}", self.build_timeout),
                     None,
-                ))
+                ));
             }
-        }?;
+        };
 
-        if !compile_output.status.success() {
-            let stderr = String::from_utf8_lossy(&compile_output.stderr);
-            let stdout = String::from_utf8_lossy(&compile_output.stdout); // Include stdout for more context
+        if !status.success() {
             tracing::error!(stderr = %stderr, stdout = %stdout, "cargo build failed");
             return Err(McpError::internal_error(
                 format!("Rust build failed: {}", stderr), // Primarily report stderr
@@ -187,6 +145,13 @@ codegen-units = 256 # Default is usually fine
             binary_path.into(), // The executable path as an OsString
         ])
     }
+
+    fn describe_files(&self) -> Vec<(String, String)> {
+        vec![
+            ("Cargo.toml".to_string(), self.cargo_toml()),
+            ("src/main.rs".to_string(), self.code.to_string()),
+        ]
+    }
 }
 
 impl RustRunner<'_> {
@@ -196,6 +161,35 @@ impl RustRunner<'_> {
             .join(&self.compile_mode) // Use the stored compile mode
             .join("mirrord-agent-code") // Match the package name in Cargo.toml
     }
+
+    fn cargo_toml(&self) -> String {
+        let mut cargo_toml = r#"
+[package]
+name = "mirrord-agent-code"
+version = "0.1.0"
+edition = "2021" # Consider updating to 2024 if appropriate, but 2021 is safer for broader compiler support
+
+[dependencies]
+reqwest = { version = "0.12", features = ["json", "blocking"] }
+serde = { version = "1.0", features = ["derive"] }
+serde_json = "1.0"
+anyhow = "1.0"
+"#
+        .to_string();
+        if self.compile_mode == "debug" {
+            cargo_toml.push_str(
+                r#"
+[profile.dev]
+opt-level = 0
+lto = false
+panic = "unwind" # 'abort' can make debugging harder
+incremental = true
+codegen-units = 256 # Default is usually fine
+"#,
+            );
+        }
+        cargo_toml
+    }
 }
 
 pub async fn run(request: Request) -> Result<String, McpError> {
@@ -203,18 +197,39 @@ pub async fn run(request: Request) -> Result<String, McpError> {
     let compile_mode =
         std::env::var("MCP_SERVICE_COMPILE_MODE").unwrap_or_else(|_| "release".to_string());
 
+    let timeout_override = parse_timeout(request.timeout.as_deref())?;
+
     // Create the runner instance
     let runner = RustRunner {
         code: &request.code,
         compile_mode,
+        build_timeout: timeout_override.unwrap_or(CARGO_BUILD_TIMEOUT),
     };
 
     // Call the shared executor function
-    execute_mirrord_run(
+    execute_mirrord_runnable(
         &runner,
-        &request.deployment,
+        Some(&request.deployment),
         &request.mirrord_config,
         "default",
+        request.dry_run,
+        timeout_override.unwrap_or(KUBECTL_TIMEOUT),
+        timeout_override.unwrap_or(MIRRORD_EXEC_TIMEOUT),
+        request.target_kind,
+        request.container.as_deref(),
+        None,
+        None,
     )
     .await
 }
+
+fn parse_timeout(timeout_str: Option<&str>) -> Result<Option<Duration>, McpError> {
+    timeout_str
+        .map(|s| {
+            humantime::parse_duration(s).map_err(|e| {
+                tracing::error!(error = %e, timeout = s, "Failed to parse timeout");
+                McpError::invalid_params(format!("Invalid timeout '{}': {}", s, e), None)
+            })
+        })
+        .transpose()
+}
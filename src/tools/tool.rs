@@ -21,6 +21,8 @@ pub struct Request {
         description = "Mirrord config in JSON format.e.g., '{\"feature\": {\"network\": {\"incoming\": {\"mode\": \"mirror\", \"ports\": [ 8888 ] } } }'."
     )]
     mirrord_config: String,
+    #[schemars(description = "Optional bytes written to the process's standard input before it's closed.")]
+    stdin: Option<String>,
 }
 #[derive(Debug, Clone)]
 pub struct MirrordService;
@@ -39,9 +41,10 @@ impl MirrordService {
     async fn run(&self, #[tool(aggr)] request: Request) -> Result<CallToolResult, McpError> {
         let result = execute_mirrord_run(
             &request.cmd_str,
-            &request.deployment,
+            Some(&request.deployment),
             &request.mirrord_config,
             "default",
+            request.stdin.as_deref(),
         )
         .await?;
         Ok(CallToolResult::success(vec![Content::text(result)]))
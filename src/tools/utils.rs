@@ -1,107 +1,87 @@
 use anyhow::Result;
 use rmcp::Error as McpError;
-use std::process::{Command, Output};
+use rmcp::schemars;
 use std::time::Duration;
-use tokio::task;
 use tokio::time::timeout;
 
-const KUBECTL_TIMEOUT: Duration = Duration::from_secs(30);
+pub(crate) const KUBECTL_TIMEOUT: Duration = Duration::from_secs(30);
 
-pub async fn get_pod_name(deployment: &str, namespace: &str) -> Result<String, McpError> {
-    let deployment_name = deployment.to_string();
-    let namespace = namespace.to_string();
-
-    let blocking_task = task::spawn_blocking(move || {
-        Command::new("kubectl")
-            .arg("get")
-            .arg("pods")
-            .arg("-n")
-            .arg(namespace)
-            .arg("-l")
-            .arg(format!("app={}", deployment_name))
-            .arg("-o")
-            .arg("jsonpath={.items[0].metadata.name}")
-            .output()
-    });
+/// The kind of workload a mirrord target path resolves to. Mirrord accepts
+/// `pod/<name>`, `deployment/<name>`, `statefulset/<name>`, `rollout/<name>`, and
+/// `service/<name>` (optionally with a `/container/<name>` suffix); only the pod form
+/// requires resolving a specific pod ourselves, since mirrord does that resolution
+/// natively for the other kinds.
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetKind {
+    #[default]
+    Pod,
+    Deployment,
+    StatefulSet,
+    Rollout,
+    Service,
+}
 
-    match timeout(KUBECTL_TIMEOUT, blocking_task).await {
-        Ok(Ok(Ok(output))) => {
-            // Timeout succeeded, spawn_blocking succeeded, Command::output succeeded
-            handle_kubectl_output(output, deployment) // Pass deployment for error message
+impl TargetKind {
+    fn as_mirrord_str(&self) -> &'static str {
+        match self {
+            TargetKind::Pod => "pod",
+            TargetKind::Deployment => "deployment",
+            TargetKind::StatefulSet => "statefulset",
+            TargetKind::Rollout => "rollout",
+            TargetKind::Service => "service",
         }
-        Ok(Ok(Err(e))) => {
-            // Timeout succeeded, spawn_blocking succeeded, Command::output failed (e.g., command not found)
-            tracing::error!(error = %e, "Failed to run kubectl command");
-            if e.kind() == std::io::ErrorKind::NotFound {
-                Err(McpError::internal_error(
-                    "Failed to execute kubectl: 'kubectl' command not found in PATH.".to_string(),
-                    None,
-                ))
-            } else {
-                Err(McpError::internal_error(
-                    format!("Failed to start kubectl process: {}", e),
-                    None,
-                ))
-            }
+    }
+}
+
+pub async fn get_pod_name(
+    deployment: &str,
+    namespace: &str,
+    timeout_duration: Duration,
+    aws_profile: Option<&str>,
+    aws_region: Option<&str>,
+) -> Result<String, McpError> {
+    match timeout(
+        timeout_duration,
+        crate::kubectl::resolve_target(deployment, namespace, aws_profile, aws_region),
+    )
+    .await
+    {
+        Ok(Ok(pod_name)) => {
+            tracing::info!("Found pod: {}", pod_name);
+            Ok(pod_name)
         }
         Ok(Err(e)) => {
-            // Timeout succeeded, but spawn_blocking failed (rare, might indicate panic)
-            tracing::error!(error = %e, "kubectl blocking task failed");
+            tracing::error!(error = %e, "Failed to resolve pod for deployment");
             Err(McpError::internal_error(
-                format!("kubectl task failed: {}", e),
+                format!("Failed to resolve pod for deployment {}: {}", deployment, e),
                 None,
             ))
         }
         Err(_) => {
-            // Timeout elapsed
-            tracing::error!("kubectl command timed out after {:?}", KUBECTL_TIMEOUT);
-            Err(McpError::internal_error(
-                format!("kubectl command timed out after {:?}", KUBECTL_TIMEOUT),
-                None,
-            ))
-        }
-    }
-}
-
-fn handle_kubectl_output(output: Output, deployment: &str) -> Result<String, McpError> {
-    if output.status.success() {
-        let pod_name = String::from_utf8(output.stdout).map_err(|e| {
-            tracing::error!(error = %e, "Invalid pod name");
-            McpError::internal_error(
-                "Failed to parse pod name from kubectl output".to_string(),
-                None,
-            )
-        })?;
-        if pod_name.is_empty() {
-            tracing::error!("No pod found for deployment");
+            tracing::error!("Pod resolution timed out after {:?}", timeout_duration);
             Err(McpError::internal_error(
-                format!("No pod found for deployment: {}", deployment),
+                format!("Pod resolution timed out after {:?}", timeout_duration),
                 None,
             ))
-        } else {
-            tracing::info!("Found pod: {}", pod_name);
-            Ok(pod_name)
         }
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string(); // Use lossy for robustness
-        tracing::error!(error = "kubectl failed", stderr = %stderr);
-        Err(McpError::internal_error(
-            format!("kubectl command failed: {}", stderr),
-            None,
-        ))
     }
 }
 
+/// Merges a target into `mirrord_config`, or removes any existing `target` from it when
+/// `deployment` is `None`/empty. With no target, mirrord spins up an independent, targetless agent in the
+/// namespace: useful for scripts that only need cluster network/DNS access without pinning
+/// to a specific pod.
 pub async fn update_mirrord_config(
     mirrord_config: &str,
-    deployment: &str,
+    deployment: Option<&str>,
     namespace: &str,
+    kubectl_timeout: Duration,
+    target_kind: TargetKind,
+    container: Option<&str>,
+    aws_profile: Option<&str>,
+    aws_region: Option<&str>,
 ) -> Result<String, McpError> {
-    let pod_name = get_pod_name(deployment, namespace).await.map_err(|e| {
-        tracing::error!(error = %e, "Failed to get pod name");
-        e
-    })?;
-
     let mut config_value: serde_json::Value =
         serde_json::from_str(mirrord_config).map_err(|e| {
             tracing::error!(error = %e, "Failed to parse mirrord config");
@@ -114,12 +94,42 @@ pub async fn update_mirrord_config(
         McpError::internal_error("Mirrord config must be a JSON object".to_string(), None)
     })?;
 
-    // Create or update the "target" field
-    let target_value = serde_json::json!({
-        "namespace": namespace,
-        "path": format!("pod/{}", pod_name)
-    });
-    config_obj.insert("target".to_string(), target_value);
+    match deployment.filter(|d| !d.is_empty()) {
+        Some(deployment) => {
+            let mut path = match target_kind {
+                TargetKind::Pod => {
+                    let pod_name =
+                        get_pod_name(deployment, namespace, kubectl_timeout, aws_profile, aws_region)
+                            .await
+                            .map_err(|e| {
+                                tracing::error!(error = %e, "Failed to get pod name");
+                                e
+                            })?;
+                    format!("pod/{}", pod_name)
+                }
+                // mirrord resolves these kinds to a pod itself; skip the round-trip.
+                TargetKind::Deployment
+                | TargetKind::StatefulSet
+                | TargetKind::Rollout
+                | TargetKind::Service => {
+                    format!("{}/{}", target_kind.as_mirrord_str(), deployment)
+                }
+            };
+            if let Some(container) = container {
+                path.push_str(&format!("/container/{}", container));
+            }
+
+            let target_value = serde_json::json!({
+                "namespace": namespace,
+                "path": path
+            });
+            config_obj.insert("target".to_string(), target_value);
+        }
+        None => {
+            tracing::info!("No deployment specified, running targetless");
+            config_obj.remove("target");
+        }
+    }
 
     // Serialize the modified config
     serde_json::to_string(&config_value).map_err(|e| {